@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::time::Duration;
 
 use bevy::prelude::*;
@@ -24,7 +25,61 @@ impl AsepriteTag {
     }
 }
 
-#[derive(Debug, Default, Component, PartialEq, Eq)]
+/// How often an animation should repeat before it stops.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationRepeat {
+    /// Loop forever (the default, matching the historic behaviour).
+    #[default]
+    Loop,
+    /// Play the tag a fixed number of times and then stop.
+    Count(u32),
+    /// Play the tag exactly once and then stop.
+    Once,
+}
+
+/// Emitted once for every entity whose (non-looping) animation reaches the end
+/// of its configured repeat count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct AsepriteAnimationFinished {
+    pub entity: Entity,
+    pub tag: Option<&'static str>,
+}
+
+/// Gameplay landmark events emitted by [`update_animations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub enum AsepriteEvent {
+    /// The playhead advanced onto `frame`.
+    FrameChanged { entity: Entity, frame: usize },
+    /// A new tag was selected (via `goto_*` or a queued tag); after
+    /// `goto_and_stop` it is selected paused.
+    TagStarted { entity: Entity, tag: Option<&'static str> },
+    /// A tag cycle completed.
+    TagEnded { entity: Entity, tag: Option<&'static str> },
+}
+
+/// Outcome of a single [`AsepriteAnimation::update`] tick.
+///
+/// `update` may cross several frame boundaries in one tick, so every frame it
+/// steps through is recorded in `frames` (in order) rather than just the frame
+/// it lands on.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnimationUpdate {
+    /// Frames stepped onto this tick, in the order they were visited.
+    pub frames: Vec<usize>,
+    /// Number of animation cycles completed this tick.
+    pub cycles: u32,
+    /// The animation reached its configured repeat count this tick.
+    pub finished: bool,
+}
+
+impl AnimationUpdate {
+    /// `true` when the sprite landed on at least one new frame this tick.
+    pub fn frame_changed(&self) -> bool {
+        !self.frames.is_empty()
+    }
+}
+
+#[derive(Debug, Component, PartialEq)]
 pub struct AsepriteAnimation {
     pub is_playing: bool,
     pub tag: Option<&'static str>,
@@ -32,6 +87,38 @@ pub struct AsepriteAnimation {
     pub forward: bool,
     pub time_elapsed: Duration,
     pub tag_changed: bool,
+    pub repeat: AnimationRepeat,
+    pub loops_completed: u32,
+    /// Tags waiting to be played once the current animation finishes.
+    pub queue: VecDeque<&'static str>,
+    /// Frame offset a pending `goto_*` request should seek to once the new tag
+    /// range is resolved against the asset.
+    pub(crate) pending_frame: Option<usize>,
+    /// Playback speed multiplier applied to the source frame delays.
+    pub speed: f32,
+    /// Overrides the tag's authored direction when set.
+    pub direction_override: Option<reader::raw::AsepriteAnimationDirection>,
+}
+
+impl Default for AsepriteAnimation {
+    fn default() -> Self {
+        Self {
+            is_playing: false,
+            tag: None,
+            current_frame: 0,
+            // PingPong starts by walking *up* the range; `next_frame`'s
+            // bottom arm would otherwise report a completed cycle on tick one.
+            forward: true,
+            time_elapsed: Duration::ZERO,
+            tag_changed: false,
+            repeat: AnimationRepeat::default(),
+            loops_completed: 0,
+            queue: VecDeque::new(),
+            pending_frame: None,
+            speed: 1.0,
+            direction_override: None,
+        }
+    }
 }
 
 impl AsepriteAnimation {
@@ -42,6 +129,21 @@ impl AsepriteAnimation {
         }
     }
 
+    /// Set the playback speed multiplier (`1.0` plays at the authored rate).
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Override the tag's authored direction at runtime.
+    pub fn with_direction(
+        mut self,
+        direction: reader::raw::AsepriteAnimationDirection,
+    ) -> Self {
+        self.direction_override = Some(direction);
+        self
+    }
+
     /// Return the first frame of the tag or 0 if no tag
     pub fn get_first_frame(&self, info: &AsepriteInfo) -> usize {
         match self.tag {
@@ -61,26 +163,32 @@ impl AsepriteAnimation {
         }
     }
 
-    fn next_frame(&mut self, info: &AsepriteInfo) {
+    /// Advance to the next frame, returning `true` when a full animation cycle
+    /// completed (i.e. the playhead wrapped to the start of its range for
+    /// Forward, to its end for Reverse, or returned to its start after a
+    /// PingPong forward-then-back pass).
+    fn next_frame(&mut self, info: &AsepriteInfo) -> bool {
         match self.tag {
             Some(tag) => {
                 let tag = match info.tags.get(tag) {
                     Some(tag) => tag,
                     None => {
                         error!("Tag {} wasn't found.", tag);
-                        return;
+                        return false;
                     }
                 };
 
                 let range = tag.frames.clone();
-                dbg!(&range);
-                match tag.animation_direction {
+                let direction = self.direction_override.unwrap_or(tag.animation_direction);
+                match direction {
                     reader::raw::AsepriteAnimationDirection::Forward => {
                         let next_frame = self.current_frame + 1;
                         if range.contains(&(next_frame as u16)) {
                             self.current_frame = next_frame;
+                            false
                         } else {
                             self.current_frame = range.start as usize;
+                            true
                         }
                     }
                     reader::raw::AsepriteAnimationDirection::Reverse => {
@@ -88,12 +196,15 @@ impl AsepriteAnimation {
                         if let Some(next_frame) = next_frame {
                             if range.contains(&((next_frame) as u16)) {
                                 self.current_frame = next_frame;
+                                false
                             } else {
                                 self.current_frame = range.end as usize - 1;
+                                true
                             }
                         } else {
                             // TODO check -1 is correct
                             self.current_frame = range.end as usize - 1;
+                            true
                         }
                     }
                     reader::raw::AsepriteAnimationDirection::PingPong => {
@@ -102,52 +213,107 @@ impl AsepriteAnimation {
                             if range.contains(&(next_frame as u16)) {
                                 self.current_frame = next_frame;
                             } else {
-                                self.current_frame = next_frame.saturating_sub(1);
+                                // Reached the top of the range: turn around and
+                                // start walking back down.
                                 self.forward = false;
+                                self.current_frame = self.current_frame.saturating_sub(1);
                             }
-                        } else {
-                            let next_frame = self.current_frame.checked_sub(1);
-                            if let Some(next_frame) = next_frame {
-                                if range.contains(&(next_frame as u16)) {
-                                    self.current_frame = next_frame
-                                }
+                            false
+                        } else if self.current_frame as u16 > range.start {
+                            self.current_frame -= 1;
+                            if self.current_frame as u16 == range.start {
+                                // Back at the bottom: a full forward-then-back
+                                // pass just finished.
+                                self.forward = true;
+                                true
+                            } else {
+                                false
                             }
-                            self.current_frame += 1;
+                        } else {
+                            // Already at the bottom of the range.
                             self.forward = true;
+                            true
                         }
                     }
                 }
             }
             None => {
-                dbg!(self.current_frame, info.frame_count);
-                self.current_frame = (self.current_frame + 1) % info.frame_count;
+                let next_frame = (self.current_frame + 1) % info.frame_count;
+                self.current_frame = next_frame;
+                next_frame == 0
             }
         }
     }
 
+    /// The number of cycles after which the animation stops, or `None` when it
+    /// loops forever.
+    fn repeat_limit(&self) -> Option<u32> {
+        match self.repeat {
+            AnimationRepeat::Loop => None,
+            AnimationRepeat::Count(count) => Some(count),
+            AnimationRepeat::Once => Some(1),
+        }
+    }
+
+    /// Returns `true` once a finite animation has played its configured number
+    /// of cycles.
+    fn has_finished(&self) -> bool {
+        matches!(self.repeat_limit(), Some(limit) if self.loops_completed >= limit)
+    }
+
     pub fn current_frame_duration(&self, info: &AsepriteInfo) -> Duration {
         // TODO store delay ms as Durations?
         Duration::from_millis(info.frame_infos[self.current_frame].delay_ms as u64)
     }
 
-    pub fn update(&mut self, info: &AsepriteInfo, dt: Duration) -> bool {
-        self.time_elapsed += dt;
+    /// The frame a finished animation should rest on. `next_frame` wraps the
+    /// playhead back to the cycle start on the final step, so when the repeat
+    /// count is reached we hold the last *displayed* frame instead (the end of
+    /// the range for Forward, its start for Reverse and PingPong).
+    fn hold_frame(&self, info: &AsepriteInfo) -> usize {
+        match self.tag.and_then(|tag| info.tags.get(tag)) {
+            Some(tag) => {
+                let range = tag.frames.clone();
+                let direction = self.direction_override.unwrap_or(tag.animation_direction);
+                match direction {
+                    reader::raw::AsepriteAnimationDirection::Reverse
+                    | reader::raw::AsepriteAnimationDirection::PingPong => range.start as usize,
+                    _ => (range.end as usize).saturating_sub(1),
+                }
+            }
+            None => info.frame_count.saturating_sub(1),
+        }
+    }
+
+    pub fn update(&mut self, info: &AsepriteInfo, dt: Duration) -> AnimationUpdate {
+        // A finite animation that already reached its repeat count rests on its
+        // last displayed frame and produces no further changes.
+        let mut result = AnimationUpdate::default();
+        if self.has_finished() {
+            return result;
+        }
+        self.time_elapsed += dt.mul_f32(self.speed.max(0.0));
         let mut current_frame_duration = self.current_frame_duration(info);
-        let mut frame_changed = false;
         while self.time_elapsed >= current_frame_duration {
             self.time_elapsed -= current_frame_duration;
-            self.next_frame(info);
+            if self.next_frame(info) {
+                self.loops_completed += 1;
+                result.cycles += 1;
+            }
+            if self.has_finished() {
+                // Hold the last displayed frame rather than the wrapped-around
+                // start frame, so one-shot attack/death/door tags rest on their
+                // final pose.
+                self.is_playing = false;
+                self.current_frame = self.hold_frame(info);
+                result.frames.push(self.current_frame);
+                result.finished = true;
+                break;
+            }
+            result.frames.push(self.current_frame);
             current_frame_duration = self.current_frame_duration(info);
-            frame_changed = true;
         }
-        dbg!(
-            dt,
-            self.time_elapsed,
-            current_frame_duration,
-            self.current_frame,
-            frame_changed
-        );
-        frame_changed
+        result
     }
 
     /// Get the current frame
@@ -155,6 +321,20 @@ impl AsepriteAnimation {
         self.current_frame
     }
 
+    /// Clamp `current_frame` into the bounds of the current tag range (or the
+    /// whole file when untagged). Used after an asset is reloaded and its frame
+    /// layout may have changed underneath a running animation.
+    fn clamp_current_frame(&mut self, info: &AsepriteInfo) {
+        let (first, last) = match self.tag.and_then(|tag| info.tags.get(tag)) {
+            Some(tag) => (
+                tag.frames.start as usize,
+                (tag.frames.end as usize).saturating_sub(1),
+            ),
+            None => (0, info.frame_count.saturating_sub(1)),
+        };
+        self.current_frame = self.current_frame.clamp(first, last);
+    }
+
     /// Start or resume playing an animation
     pub fn play(&mut self) {
         self.is_playing = true;
@@ -179,6 +359,74 @@ impl AsepriteAnimation {
     pub fn toggle(&mut self) {
         self.is_playing = !self.is_playing;
     }
+
+    /// Queue a tag to play once the current (non-looping) animation finishes.
+    pub fn queue(&mut self, tag: &'static str) {
+        self.queue.push_back(tag);
+    }
+
+    /// Keep playing the current animation, then play `tag` when it finishes.
+    pub fn play_then(&mut self, tag: &'static str) {
+        self.is_playing = true;
+        self.queue.push_back(tag);
+    }
+
+    /// Switch to `tag` immediately and resume playing from its first frame.
+    pub fn goto_and_play(&mut self, tag: &'static str) {
+        self.switch_tag(tag);
+        self.pending_frame = None;
+        self.is_playing = true;
+    }
+
+    /// Switch to `tag`, seek to `frame` within its range and pause.
+    pub fn goto_and_stop(&mut self, tag: &'static str, frame: usize) {
+        self.switch_tag(tag);
+        self.pending_frame = Some(frame);
+        self.is_playing = false;
+    }
+
+    /// Reset the playback state for a freshly selected `tag`. The actual frame
+    /// is resolved by `update_animations` once the asset is available.
+    fn switch_tag(&mut self, tag: &'static str) {
+        self.tag = Some(tag);
+        self.tag_changed = true;
+        self.forward = true;
+        self.loops_completed = 0;
+        self.time_elapsed = Duration::ZERO;
+    }
+
+    /// Resolve the absolute frame index a pending `goto_*` request should land
+    /// on, clamped into the current tag range.
+    fn resolve_tag_frame(&mut self, info: &AsepriteInfo) -> usize {
+        let first = self.get_first_frame(info);
+        match self.pending_frame.take() {
+            Some(offset) => match self.tag.and_then(|tag| info.tags.get(tag)) {
+                Some(tag) => {
+                    let last = (tag.frames.end as usize).saturating_sub(1);
+                    (first + offset).min(last)
+                }
+                None => first,
+            },
+            None => first,
+        }
+    }
+}
+
+/// Registers the animation systems together with the events they write.
+///
+/// `update_animations` writes [`AsepriteAnimationFinished`] and [`AsepriteEvent`]
+/// through `EventWriter`, so scheduling it and adding those `Events` resources
+/// has to happen together — splitting them across plugins lets a caller wire up
+/// the writer without the resource and panic. The crate's top-level plugin adds
+/// this plugin rather than scheduling `update_animations` itself.
+pub struct AsepriteAnimationPlugin;
+
+impl Plugin for AsepriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AsepriteAnimationFinished>()
+            .add_event::<AsepriteEvent>()
+            .add_systems(Update, (update_animations, reload_animations));
+    }
 }
 
 pub(crate) fn update_animations(
@@ -186,12 +434,15 @@ pub(crate) fn update_animations(
     aseprites: Res<Assets<Aseprite>>,
     atlases: Res<Assets<TextureAtlas>>,
     mut aseprites_query: Query<(
+        Entity,
         &Handle<Aseprite>,
         &mut AsepriteAnimation,
         &mut TextureAtlasSprite,
     )>,
+    mut finished_events: EventWriter<AsepriteAnimationFinished>,
+    mut anim_events: EventWriter<AsepriteEvent>,
 ) {
-    for (handle, mut animation, mut sprite) in aseprites_query.iter_mut() {
+    for (entity, handle, mut animation, mut sprite) in aseprites_query.iter_mut() {
         let aseprite = match aseprites.get(handle) {
             Some(aseprite) => aseprite,
             None => {
@@ -220,8 +471,96 @@ pub(crate) fn update_animations(
                 continue;
             }
         };
-        if animation.update(info, time.delta()) {
+        // A freshly selected tag (via `goto_*`) seeks immediately and refreshes
+        // the sprite before any time accumulates.
+        if animation.tag_changed {
+            animation.current_frame = animation.resolve_tag_frame(info);
+            animation.tag_changed = false;
+            sprite.index = atlas
+                .get_texture_index(&aseprite.frame_handles[animation.current_frame])
+                .unwrap();
+            anim_events.send(AsepriteEvent::TagStarted {
+                entity,
+                tag: animation.tag,
+            });
+        }
+
+        // A paused animation (e.g. after `goto_and_stop` / `pause`) holds its
+        // frame: `update` never runs, so no time accumulates and it can't
+        // resume itself on a later tick.
+        if !animation.is_playing {
+            continue;
+        }
+
+        let update = animation.update(info, time.delta());
+        if update.frame_changed() {
             sprite.index = atlas.get_texture_index(&aseprite.frame_handles[animation.current_frame]).unwrap();
         }
+        // Report every frame the tick stepped through, in order, so landmark
+        // frames aren't dropped when the animation crosses multiple boundaries.
+        for frame in &update.frames {
+            anim_events.send(AsepriteEvent::FrameChanged {
+                entity,
+                frame: *frame,
+            });
+        }
+        for _ in 0..update.cycles {
+            anim_events.send(AsepriteEvent::TagEnded {
+                entity,
+                tag: animation.tag,
+            });
+        }
+        if update.finished {
+            finished_events.send(AsepriteAnimationFinished {
+                entity,
+                tag: animation.tag,
+            });
+            // Chain into the next queued tag, or stay stopped when drained.
+            if let Some(next) = animation.queue.pop_front() {
+                animation.goto_and_play(next);
+            }
+        }
+    }
+}
+
+/// Re-syncs running animations when their `.aseprite` source is edited and
+/// reloaded, so artists get live iteration instead of stale frame indices or
+/// out-of-range panics.
+pub(crate) fn reload_animations(
+    mut asset_events: EventReader<AssetEvent<Aseprite>>,
+    aseprites: Res<Assets<Aseprite>>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut aseprites_query: Query<(&Handle<Aseprite>, &mut AsepriteAnimation, &mut TextureAtlasSprite)>,
+) {
+    for event in asset_events.iter() {
+        let modified = match event {
+            AssetEvent::Modified { handle } => handle,
+            _ => continue,
+        };
+
+        let aseprite = match aseprites.get(modified) {
+            Some(aseprite) => aseprite,
+            None => continue,
+        };
+        let info = match &aseprite.info {
+            Some(info) => info,
+            None => continue,
+        };
+        let atlas = match aseprite.atlas.as_ref().and_then(|handle| atlases.get(handle)) {
+            Some(atlas) => atlas,
+            None => continue,
+        };
+
+        for (handle, mut animation, mut sprite) in aseprites_query.iter_mut() {
+            if handle != modified {
+                continue;
+            }
+
+            animation.clamp_current_frame(info);
+            animation.time_elapsed = Duration::ZERO;
+            sprite.index = atlas
+                .get_texture_index(&aseprite.frame_handles[animation.current_frame])
+                .unwrap();
+        }
     }
 }